@@ -19,14 +19,141 @@
 #![deny(anonymous_parameters, macro_use_extern_crate, pointer_structural_match)]
 #![deny(missing_docs)]
 
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
 // A type for our predicate functions, which take a `Path` and return a `bool`.
-type Predicate = dyn Fn(&Path) -> bool;
+//
+// The `Send + Sync` bounds let the same predicates drive the parallel walker
+// (see [`FileFilter::walk_parallel`]); they are cheap to satisfy for the plain
+// functions and closures used in practice.
+type Predicate = dyn Fn(&Path) -> bool + Send + Sync;
+
+/// Errors produced by the walker itself.
+///
+/// File system errors are propagated as-is through the `Box<dyn Error>` result
+/// type; this enum only covers conditions the walker detects on its own.
+#[derive(Debug)]
+pub enum FileFilterError {
+    /// A symlink was followed back into one of its own ancestor directories,
+    /// which would otherwise cause the walk to loop forever.
+    LoopDetected {
+        /// The ancestor directory that `child` points back into.
+        ancestor: PathBuf,
+        /// The entry whose target re-enters `ancestor`.
+        child: PathBuf,
+    },
+}
+
+impl fmt::Display for FileFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileFilterError::LoopDetected { ancestor, child } => write!(
+                f,
+                "file system loop detected: {} points to ancestor {}",
+                child.display(),
+                ancestor.display()
+            ),
+        }
+    }
+}
+
+impl Error for FileFilterError {}
+
+/// A unique identity for a directory, used to detect symlink loops.
+///
+/// On Unix this is the `(device id, inode)` pair; on Windows it is the
+/// `(volume serial number, file index)` pair from `BY_HANDLE_FILE_INFORMATION`.
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    (meta.dev(), meta.ino())
+}
+
+#[cfg(windows)]
+fn file_id(meta: &fs::Metadata) -> FileId {
+    use std::os::windows::fs::MetadataExt;
+    (
+        u64::from(meta.volume_serial_number().unwrap_or(0)),
+        meta.file_index().unwrap_or(0),
+    )
+}
+
+/// A matched entry yielded by [`FileFilter`].
+///
+/// Carries the entry's `path` together with its `depth` below the root, where
+/// the root itself is depth `0`, its immediate children depth `1`, and so on.
+/// Use [`Entry::path`] for the common case of inspecting the path and
+/// [`Entry::depth`] when filtering by how deep the entry sits.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: PathBuf,
+    depth: usize,
+}
+
+impl Entry {
+    /// The path of this entry.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The depth of this entry below the root (root = `0`).
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Consume the entry, returning its owned path.
+    #[must_use]
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+}
+
+/// A directory on the traversal stack.
+///
+/// The handle may be live (an open `fs::ReadDir`) or temporarily closed to
+/// stay within the `max_open` descriptor budget. Either way the entry
+/// remembers its `path` and how many children it has already `consumed`, so a
+/// closed handle can be re-opened exactly where it left off.
+struct StackEntry {
+    /// The directory this entry reads.
+    path: PathBuf,
+    /// The depth of this directory below the root (root = `0`).
+    depth: usize,
+    /// How many entries of this directory have already been yielded by the
+    /// underlying `ReadDir`.
+    consumed: usize,
+    /// The live or closed handle.
+    handle: Handle,
+}
+
+/// The open/closed state of a [`StackEntry`]'s directory handle.
+enum Handle {
+    /// A live `fs::ReadDir` that holds an open file descriptor.
+    Open(fs::ReadDir),
+    /// A handle that has been closed to free its descriptor; re-opened on
+    /// demand by calling `fs::read_dir` again and skipping `consumed` entries.
+    Closed,
+    /// The directory was drained and sorted up front; its entries are served
+    /// from this vector using `consumed` as the cursor. Holds no descriptor,
+    /// so it is exempt from the `max_open` budget.
+    Sorted(Vec<PathBuf>),
+}
+
+/// A comparator used to order the entries of each directory.
+type Comparator = dyn FnMut(&Path, &Path) -> Ordering;
 
 /// A file system walker.
 ///
@@ -34,14 +161,47 @@ type Predicate = dyn Fn(&Path) -> bool;
 /// based on the given configuration
 pub struct FileFilter {
     /// The predicate to use to filter files.
-    predicates: Vec<Box<Predicate>>,
+    predicates: Vec<Arc<Predicate>>,
+    /// Predicates applied to both files and directories before they are
+    /// visited. A directory rejected here is not descended into, pruning its
+    /// entire subtree.
+    entry_filters: Vec<Arc<Predicate>>,
     /// The start path.
     ///
     /// This is only `Some(...)` at the beginning.
     /// After the first iteration, this is always `None`.
     start: Option<PathBuf>,
     /// The stack of directories to traverse
-    stack: Vec<fs::ReadDir>,
+    stack: Vec<StackEntry>,
+    /// The maximum number of directory handles kept open at once.
+    ///
+    /// Defaults to `usize::MAX` (effectively unlimited). Lowering this caps the
+    /// number of file descriptors a deep walk consumes, at the cost of
+    /// re-opening directories as the walk revisits them.
+    max_open: usize,
+    /// The identity and path of every ancestor directory currently on the
+    /// stack, used to detect symlink loops when `follow_links` is enabled.
+    ///
+    /// Kept in lockstep with `stack`: an entry is pushed when a directory is
+    /// pushed and popped when its `ReadDir` is popped.
+    ancestors: Vec<(FileId, PathBuf)>,
+    /// Whether to descend into directories reached through symbolic links.
+    follow_links: bool,
+    /// The minimum depth an entry must have to be yielded (root = `0`).
+    min_depth: usize,
+    /// The maximum depth to descend to; directories at this depth are not
+    /// traversed.
+    max_depth: usize,
+    /// An optional comparator used to sort each directory's entries for
+    /// reproducible ordering.
+    sort_by: Option<Box<Comparator>>,
+    /// Whether to yield a directory after its contents instead of never.
+    contents_first: bool,
+    /// Whether to stay on the root's filesystem and not cross mount points.
+    same_file_system: bool,
+    /// The device id of the root, recorded on the first directory push and used
+    /// to detect mount-point crossings when `same_file_system` is set.
+    root_device: Option<u64>,
 }
 
 impl FileFilter {
@@ -50,8 +210,18 @@ impl FileFilter {
     pub fn new<P: AsRef<Path>>(root: P) -> Self {
         FileFilter {
             predicates: vec![],
+            entry_filters: vec![],
             start: Some(root.as_ref().to_path_buf()),
             stack: vec![],
+            max_open: usize::MAX,
+            ancestors: vec![],
+            follow_links: false,
+            min_depth: 0,
+            max_depth: usize::MAX,
+            sort_by: None,
+            contents_first: false,
+            same_file_system: false,
+            root_device: None,
         }
     }
 
@@ -59,64 +229,458 @@ impl FileFilter {
     ///
     /// Returns `self` to allow chaining.
     #[must_use]
-    pub fn add_filter(mut self, predicate: impl Fn(&Path) -> bool + 'static) -> Self {
-        self.predicates.push(Box::new(predicate));
+    pub fn add_filter(mut self, predicate: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.predicates.push(Arc::new(predicate));
         self
     }
 
+    /// Sort each directory's entries with `cmp` before visiting them.
+    ///
+    /// `fs::read_dir` yields entries in an unspecified, platform-dependent
+    /// order. Supplying a comparator makes the walk reproducible: each
+    /// directory is drained and sorted as it is pushed, then served from the
+    /// sorted vector. See [`sort_by_file_name`](Self::sort_by_file_name) for
+    /// the common case.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub fn sort_by(mut self, cmp: impl FnMut(&Path, &Path) -> Ordering + 'static) -> Self {
+        self.sort_by = Some(Box::new(cmp));
+        self
+    }
+
+    /// Sort each directory's entries lexically by file name.
+    ///
+    /// A convenience wrapper around [`sort_by`](Self::sort_by).
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub fn sort_by_file_name(self) -> Self {
+        self.sort_by(|a, b| a.file_name().cmp(&b.file_name()))
+    }
+
+    /// Yield a directory *after* its contents rather than never.
+    ///
+    /// Off by default, in which case directories are only traversed, never
+    /// yielded. Turning this on produces a bottom-up order — every entry of a
+    /// directory is yielded before the directory itself — which suits
+    /// recursive deletion and other bottom-up processing. The depth window
+    /// still applies to the directory entry.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn contents_first(mut self, yes: bool) -> Self {
+        self.contents_first = yes;
+        self
+    }
+
+    /// Confine the walk to the filesystem the root lives on.
+    ///
+    /// When enabled, the device id of the root directory is recorded and any
+    /// subdirectory residing on a different device — a mount point, network
+    /// share, or bind mount — is visited but not descended into. This keeps a
+    /// walk of `/` from wandering into `/proc`, `/sys`, or network mounts.
+    /// Within a single filesystem the option is a no-op.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn same_file_system(mut self, yes: bool) -> Self {
+        self.same_file_system = yes;
+        self
+    }
+
+    /// Prune entries before they are visited.
+    ///
+    /// Unlike [`add_filter`](Self::add_filter), which only decides whether a
+    /// *file* is yielded, `filter_entry` is consulted for directories too:
+    /// when it returns `false` for a directory, that directory is neither
+    /// yielded nor descended into, so none of its contents are read. This
+    /// avoids the `read_dir` syscalls for large ignored subtrees such as
+    /// `.git`, `node_modules`, or `target`.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub fn filter_entry(
+        mut self,
+        predicate: impl Fn(&Path) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.entry_filters.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only yield entries at or below this depth (root = `0`).
+    ///
+    /// Entries shallower than `depth` are skipped while the walk still descends
+    /// through them to reach deeper matches.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn min_depth(mut self, depth: usize) -> Self {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Do not descend deeper than this many levels below the root (root = `0`).
+    ///
+    /// A directory sitting at `depth` is still visited, but its contents are
+    /// not, so no entry deeper than `depth` is ever yielded.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Cap the number of directory handles kept open simultaneously.
+    ///
+    /// A plain recursive walk holds one open descriptor per directory on the
+    /// current path, which can exhaust the process' file-descriptor limit on
+    /// very deep trees. Setting a bound causes older handles to be closed and
+    /// transparently re-opened later, trading a few extra `read_dir` calls for
+    /// a fixed descriptor budget. Values below `1` are treated as `1`.
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn max_open(mut self, n: usize) -> Self {
+        self.max_open = if n < 1 { 1 } else { n };
+        self
+    }
+
+    /// Follow symbolic links that point at directories, descending into their
+    /// targets as if they were ordinary subdirectories.
+    ///
+    /// Disabled by default, in which case a symlink is treated like a leaf and
+    /// tested against the predicates rather than traversed. When enabled, the
+    /// walker guards against infinite loops by refusing to re-enter any
+    /// ancestor directory (see [`FileFilterError::LoopDetected`]).
+    ///
+    /// Returns `self` to allow chaining.
+    #[must_use]
+    pub const fn follow_links(mut self, yes: bool) -> Self {
+        self.follow_links = yes;
+        self
+    }
+
+    /// Walk the tree in parallel across a pool of worker threads.
+    ///
+    /// Each worker pops a directory from a shared queue, reads it, applies the
+    /// [`filter_entry`](Self::filter_entry) prune check, pushes child
+    /// directories back onto the queue, and sends matching files over a
+    /// channel. The returned [`ParallelWalk`] is an `IntoIterator` draining
+    /// that channel, so callers keep an iterator-shaped API.
+    ///
+    /// The pool defaults to [`std::thread::available_parallelism`] workers.
+    /// The predicates registered with [`add_filter`](Self::add_filter) and
+    /// [`filter_entry`](Self::filter_entry) run on those threads, which is why
+    /// they are `Send + Sync`.
+    ///
+    /// Unlike the sequential iterator the **order is non-deterministic**, and
+    /// the `follow_links`, `max_open`, `sort_by`, `contents_first`, and
+    /// `same_file_system` options do not apply; directories are never yielded.
+    ///
+    /// # Panics
+    ///
+    /// Does not panic in practice: the shared work-queue lock is taken here
+    /// only before any worker thread is spawned, so it cannot be poisoned yet.
+    #[must_use]
+    pub fn walk_parallel(self) -> ParallelWalk {
+        let workers = thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+        self.walk_parallel_with(workers)
+    }
+
+    /// Like [`walk_parallel`](Self::walk_parallel) but with an explicit worker
+    /// count. Exposed separately so the count is testable without depending on
+    /// the host's CPU topology.
+    ///
+    /// # Panics
+    ///
+    /// Does not panic in practice: the shared work-queue lock is taken here
+    /// only before any worker thread is spawned, so it cannot be poisoned yet.
+    #[must_use]
+    pub fn walk_parallel_with(self, workers: usize) -> ParallelWalk {
+        let workers = workers.max(1);
+        let config = Arc::new(ParallelConfig {
+            predicates: self.predicates,
+            entry_filters: self.entry_filters,
+            min_depth: self.min_depth,
+            max_depth: self.max_depth,
+        });
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                active: 0,
+            }),
+            signal: Condvar::new(),
+        });
+        let (tx, rx) = mpsc::channel::<SendResult>();
+
+        // Seed the queue with the root. A real directory is queued for
+        // traversal; anything else (including a symlink, which is not followed
+        // by default) is matched directly as a leaf.
+        if let Some(root) = self.start {
+            if config.accept_entry(&root) {
+                let is_dir = fs::symlink_metadata(&root).map(|m| m.is_dir());
+                match is_dir {
+                    Ok(true) => {
+                        let mut state = shared.state.lock().unwrap();
+                        state.active += 1;
+                        state.queue.push_back((root, 0));
+                    }
+                    Ok(false) => {
+                        if config.accept_file(&root, 0) {
+                            let _ = tx.send(Ok(root));
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(Box::new(e)));
+                    }
+                }
+            }
+        }
+
+        for _ in 0..workers {
+            let shared = Arc::clone(&shared);
+            let config = Arc::clone(&config);
+            let tx = tx.clone();
+            thread::spawn(move || worker(&shared, &config, &tx));
+        }
+        // Drop our own sender so the channel closes once all workers finish.
+        drop(tx);
+
+        ParallelWalk { rx }
+    }
+
     /// Process a single entry and check if it matches the predicates.
-    fn process_entry(&mut self, path: PathBuf) -> Option<Result<PathBuf>> {
-        if path.is_dir() {
-            // Push directories onto the stack
-            if let Err(e) = self.push(&path) {
-                return Some(Err(e));
+    ///
+    /// `depth` is the depth of `path` below the root (root = `0`).
+    fn process_entry(&mut self, path: PathBuf, depth: usize) -> Option<Result<Entry>> {
+        // Prune before doing any work: a rejected directory is never pushed, so
+        // its subtree costs no further syscalls.
+        if !self.entry_filters.iter().all(|f| f(&path)) {
+            return None;
+        }
+
+        // `is_dir` follows symlinks, so restrict directory handling to the
+        // cases we actually want to descend into: real directories always, and
+        // symlinked directories only when `follow_links` is set.
+        let meta = match fs::symlink_metadata(&path) {
+            Ok(meta) => meta,
+            Err(e) => return Some(Err(Box::new(e))),
+        };
+        let descend = if meta.file_type().is_symlink() {
+            self.follow_links && path.is_dir()
+        } else {
+            meta.is_dir()
+        };
+
+        if descend {
+            // When following links we might walk back into an ancestor
+            // directory; stat the target and bail out if its identity already
+            // appears on the stack.
+            if self.follow_links {
+                match fs::metadata(&path) {
+                    Ok(target) => {
+                        let id = file_id(&target);
+                        if let Some((_, ancestor)) =
+                            self.ancestors.iter().rev().find(|(a, _)| *a == id)
+                        {
+                            return Some(Err(Box::new(FileFilterError::LoopDetected {
+                                ancestor: ancestor.clone(),
+                                child: path,
+                            })));
+                        }
+                    }
+                    Err(e) => return Some(Err(Box::new(e))),
+                }
+            }
+
+            // Stop at mount points when confined to a single filesystem. The
+            // root's device is recorded the first time we get here; any later
+            // directory on a different device is visited but not traversed.
+            if self.same_file_system {
+                let dev = match fs::metadata(&path) {
+                    Ok(meta) => file_id(&meta).0,
+                    Err(e) => return Some(Err(Box::new(e))),
+                };
+                match self.root_device {
+                    None => self.root_device = Some(dev),
+                    Some(root) if dev != root => {
+                        if self.contents_first && self.in_depth_window(depth) {
+                            return Some(Ok(Entry { path, depth }));
+                        }
+                        return None;
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            // Descend only while the children would stay within `max_depth`.
+            if depth < self.max_depth {
+                // Push the directory at its *own* depth; `next` adds one when
+                // deriving each child's depth, so children land at `depth + 1`.
+                if let Err(e) = self.push(&path, depth) {
+                    return Some(Err(e));
+                }
+                // When `contents_first` is set, the directory is yielded later,
+                // once its contents have been drained (see the exhaustion
+                // branch in `next`).
+                return None;
+            }
+            // At `max_depth` the directory is visited but not descended. In
+            // `contents_first` mode it still needs to be yielded, since it has
+            // no contents to come before.
+            if self.contents_first && self.in_depth_window(depth) {
+                return Some(Ok(Entry { path, depth }));
             }
             None
         } else {
-            // Check files against the predicates
-            if self.predicates.iter().all(|f| f(&path)) {
-                Some(Ok(path))
+            // Check files against the depth window and the predicates.
+            if self.in_depth_window(depth) && self.predicates.iter().all(|f| f(&path)) {
+                Some(Ok(Entry { path, depth }))
             } else {
                 None
             }
         }
     }
 
-    /// Read dir and push it onto the stack
-    fn push(&mut self, entry: &PathBuf) -> Result<()> {
-        let rd = fs::read_dir(entry)?;
-        self.stack.push(rd);
+    /// Whether an entry at `depth` falls inside the configured depth window.
+    const fn in_depth_window(&self, depth: usize) -> bool {
+        depth >= self.min_depth && depth <= self.max_depth
+    }
+
+    /// Read dir and push it onto the stack at the given `depth`.
+    fn push(&mut self, entry: &PathBuf, depth: usize) -> Result<()> {
+        let handle = if let Some(cmp) = self.sort_by.as_mut() {
+            // Drain and sort eagerly; no descriptor is kept open afterwards.
+            let mut paths = fs::read_dir(entry)?
+                .map(|e| e.map(|d| d.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            paths.sort_by(|a, b| cmp(a, b));
+            Handle::Sorted(paths)
+        } else {
+            // Make room for the new handle within the descriptor budget before
+            // opening it. `keep` is out of range, so every existing entry is
+            // eligible to be closed.
+            self.ensure_open_capacity(self.stack.len());
+            Handle::Open(fs::read_dir(entry)?)
+        };
+        if self.follow_links {
+            let id = file_id(&fs::metadata(entry)?);
+            self.ancestors.push((id, entry.clone()));
+        }
+        self.stack.push(StackEntry {
+            path: entry.clone(),
+            depth,
+            consumed: 0,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Pop the top of the stack, keeping the ancestor set in lockstep.
+    fn pop(&mut self) {
+        self.stack.pop();
+        if self.follow_links {
+            self.ancestors.pop();
+        }
+    }
+
+    /// Number of currently open directory handles.
+    fn open_count(&self) -> usize {
+        self.stack
+            .iter()
+            .filter(|e| matches!(e.handle, Handle::Open(_)))
+            .count()
+    }
+
+    /// Close the oldest open handles (other than `keep`) until there is room
+    /// for one more within the `max_open` budget.
+    fn ensure_open_capacity(&mut self, keep: usize) {
+        while self.open_count() >= self.max_open {
+            let victim = (0..self.stack.len())
+                .find(|&i| i != keep && matches!(self.stack[i].handle, Handle::Open(_)));
+            match victim {
+                Some(i) => self.stack[i].handle = Handle::Closed,
+                // Nothing left to close (only `keep` is open); accept the
+                // temporary overshoot rather than stalling.
+                None => break,
+            }
+        }
+    }
+
+    /// Re-open the closed handle at `idx`, fast-forwarding past the entries it
+    /// had already consumed before it was closed.
+    fn reopen(&mut self, idx: usize) -> Result<()> {
+        self.ensure_open_capacity(idx);
+        let consumed = self.stack[idx].consumed;
+        let mut rd = fs::read_dir(&self.stack[idx].path)?;
+        for _ in 0..consumed {
+            rd.next();
+        }
+        self.stack[idx].handle = Handle::Open(rd);
         Ok(())
     }
 }
 
 impl Iterator for FileFilter {
-    type Item = Result<PathBuf>;
+    type Item = Result<Entry>;
 
     fn next(&mut self) -> Option<Self::Item> {
         // Takes the value out of the option, leaving a `None` in its place.
         // In the next iteration, this will be `None` and we'll skip this block.
         if let Some(start) = self.start.take() {
-            // Process the initial start path
-            if let Some(result) = self.process_entry(start) {
+            // Process the initial start path at the root depth.
+            if let Some(result) = self.process_entry(start, 0) {
                 return Some(result);
             }
         }
 
-        // `last_mut` returns a mutable pointer to the last item in the slice.
-        // We need this to be able to call `rd.next()`, which mutates the
-        // iterator.
-        while let Some(rd) = self.stack.last_mut() {
-            match rd.next() {
-                Some(Ok(entry)) => {
-                    if let Some(result) = self.process_entry(entry.path()) {
+        // Drive the top of the stack. We index rather than hold a `last_mut`
+        // borrow because `process_entry` may push new directories (and close
+        // older handles) while we are reading.
+        while let Some(idx) = self.stack.len().checked_sub(1) {
+            // The top handle may have been closed to stay within `max_open`;
+            // re-open it before reading.
+            if matches!(self.stack[idx].handle, Handle::Closed) {
+                if let Err(e) = self.reopen(idx) {
+                    self.pop();
+                    return Some(Err(e));
+                }
+            }
+
+            let cursor = self.stack[idx].consumed;
+            let next: Option<Result<PathBuf>> = match &mut self.stack[idx].handle {
+                Handle::Open(rd) => rd
+                    .next()
+                    .map(|r| r.map(|d| d.path()).map_err(|e| Box::new(e) as Box<dyn Error>)),
+                Handle::Sorted(paths) => paths.get(cursor).cloned().map(Ok),
+                Handle::Closed => unreachable!("handle was just re-opened"),
+            };
+
+            match next {
+                Some(Ok(path)) => {
+                    self.stack[idx].consumed += 1;
+                    let depth = self.stack[idx].depth + 1;
+                    if let Some(result) = self.process_entry(path, depth) {
                         return Some(result);
                     }
                 }
-                Some(Err(e)) => return Some(Err(Box::new(e))),
+                Some(Err(e)) => {
+                    // The failed entry still advanced the `ReadDir`, so count it
+                    // for a later re-open to skip.
+                    self.stack[idx].consumed += 1;
+                    return Some(Err(e));
+                }
                 None => {
-                    // Pop empty directory
-                    self.stack.pop();
+                    // Directory exhausted. In `contents_first` mode yield it now
+                    // — after everything it contained.
+                    let path = self.stack[idx].path.clone();
+                    let depth = self.stack[idx].depth;
+                    self.pop();
+                    if self.contents_first && self.in_depth_window(depth) {
+                        return Some(Ok(Entry { path, depth }));
+                    }
                 }
             }
         }
@@ -126,6 +690,169 @@ impl Iterator for FileFilter {
     }
 }
 
+/// The error type sent across the worker channel.
+///
+/// `Box<dyn Error>` is not `Send`, so the parallel walker carries a
+/// thread-safe error internally and widens it to the crate's [`Result`] at the
+/// iterator boundary.
+type SendResult = std::result::Result<PathBuf, Box<dyn Error + Send + Sync>>;
+
+/// The immutable configuration shared by every parallel worker.
+struct ParallelConfig {
+    predicates: Vec<Arc<Predicate>>,
+    entry_filters: Vec<Arc<Predicate>>,
+    min_depth: usize,
+    max_depth: usize,
+}
+
+impl ParallelConfig {
+    /// Whether an entry (file or directory) survives the prune predicates.
+    fn accept_entry(&self, path: &Path) -> bool {
+        self.entry_filters.iter().all(|f| f(path))
+    }
+
+    /// Whether a file at `depth` should be yielded.
+    fn accept_file(&self, path: &Path, depth: usize) -> bool {
+        depth >= self.min_depth
+            && depth <= self.max_depth
+            && self.predicates.iter().all(|f| f(path))
+    }
+}
+
+/// The mutable work queue shared by the workers, guarded by a mutex.
+struct State {
+    /// Directories still to be traversed, with their depth.
+    queue: VecDeque<(PathBuf, usize)>,
+    /// The number of directories queued or currently being processed. The walk
+    /// is complete once this reaches zero.
+    active: usize,
+}
+
+/// The synchronisation shared between workers.
+struct Shared {
+    state: Mutex<State>,
+    signal: Condvar,
+}
+
+/// A worker thread: pop directories, fan out children, send matching files.
+fn worker(shared: &Shared, config: &ParallelConfig, tx: &mpsc::Sender<SendResult>) {
+    loop {
+        // Claim the next directory, or exit once the queue is drained and no
+        // other worker is still producing.
+        let (dir, depth) = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if let Some(item) = state.queue.pop_front() {
+                    break item;
+                }
+                if state.active == 0 {
+                    // Everything is done; make sure the other workers wake to
+                    // observe it too.
+                    shared.signal.notify_all();
+                    return;
+                }
+                state = shared.signal.wait(state).unwrap();
+            }
+        };
+
+        match fs::read_dir(&dir) {
+            Ok(rd) => {
+                for entry in rd {
+                    match entry {
+                        Ok(entry) => process_parallel(shared, config, tx, &entry.path(), depth + 1),
+                        Err(e) => {
+                            let _ = tx.send(Err(Box::new(e)));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(Err(Box::new(e)));
+            }
+        }
+
+        // Finished this directory; wake a waiter if the walk just ended.
+        let mut state = shared.state.lock().unwrap();
+        state.active -= 1;
+        if state.active == 0 {
+            shared.signal.notify_all();
+        }
+    }
+}
+
+/// Classify one child entry: prune, enqueue a subdirectory, or send a file.
+fn process_parallel(
+    shared: &Shared,
+    config: &ParallelConfig,
+    tx: &mpsc::Sender<SendResult>,
+    path: &Path,
+    depth: usize,
+) {
+    if !config.accept_entry(path) {
+        return;
+    }
+    // Mirror the sequential walker's default of not following symlinks: a
+    // symlink is treated as a leaf and tested against the file predicates
+    // rather than traversed. This also keeps the pool from spinning forever on
+    // a symlink cycle.
+    let descend = match fs::symlink_metadata(path) {
+        Ok(meta) => meta.is_dir(),
+        Err(e) => {
+            let _ = tx.send(Err(Box::new(e)));
+            return;
+        }
+    };
+    if descend {
+        // Descend only while this directory's own children stay within
+        // `max_depth`.
+        if depth < config.max_depth {
+            let mut state = shared.state.lock().unwrap();
+            state.active += 1;
+            state.queue.push_back((path.to_path_buf(), depth));
+            shared.signal.notify_one();
+        }
+    } else if config.accept_file(path, depth) {
+        let _ = tx.send(Ok(path.to_path_buf()));
+    }
+}
+
+/// A consuming driver for [`FileFilter::walk_parallel`].
+///
+/// Implements [`IntoIterator`] over the matched files; ordering is
+/// non-deterministic. Dropping the walk simply stops draining the channel;
+/// the worker threads are not stopped early but keep walking the rest of the
+/// tree, discarding their results, and exit once the queue is drained.
+pub struct ParallelWalk {
+    rx: mpsc::Receiver<SendResult>,
+}
+
+impl IntoIterator for ParallelWalk {
+    type Item = Result<PathBuf>;
+    type IntoIter = ParallelIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ParallelIter { rx: self.rx }
+    }
+}
+
+/// The iterator returned by [`ParallelWalk::into_iter`].
+pub struct ParallelIter {
+    rx: mpsc::Receiver<SendResult>,
+}
+
+impl Iterator for ParallelIter {
+    type Item = Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // `recv` blocks until a worker produces a result and returns `Err` once
+        // every sender has been dropped, i.e. the walk is complete.
+        self.rx
+            .recv()
+            .ok()
+            .map(|r| r.map_err(|e| e as Box<dyn Error>))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,7 +877,7 @@ mod tests {
         assert!(!entries.is_empty());
 
         for entry in entries {
-            assert_eq!(entry.extension().unwrap().to_str().unwrap(), "txt");
+            assert_eq!(entry.path().extension().unwrap().to_str().unwrap(), "txt");
         }
         Ok(())
     }
@@ -163,7 +890,7 @@ mod tests {
         assert!(!entries.is_empty());
 
         for entry in entries {
-            let file_name = entry.file_name().unwrap().to_str().unwrap();
+            let file_name = entry.path().file_name().unwrap().to_str().unwrap();
             assert!(file_name.starts_with("prefix_"));
         }
         Ok(())
@@ -180,10 +907,263 @@ mod tests {
         assert!(!entries.is_empty());
 
         for entry in entries {
-            let file_name = entry.file_name().unwrap().to_str().unwrap();
+            let file_name = entry.path().file_name().unwrap().to_str().unwrap();
             assert!(file_name.starts_with("prefix_"));
-            assert_eq!(entry.extension().unwrap().to_str().unwrap(), "txt");
+            assert_eq!(entry.path().extension().unwrap().to_str().unwrap(), "txt");
+        }
+        Ok(())
+    }
+
+    /// A fixture directory that is removed when it goes out of scope.
+    ///
+    /// Using a `Drop` guard keeps the cleanup RAII, so a panicking assertion
+    /// cannot leak the temp tree, and keeps each test's setup to the lines that
+    /// actually describe the fixture.
+    struct TempTree {
+        root: PathBuf,
+    }
+
+    impl TempTree {
+        /// Create a fresh, empty fixture directory tagged with `tag`.
+        fn new(tag: &str) -> Result<Self> {
+            let root =
+                std::env::temp_dir().join(format!("filefilter_{tag}_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root)?;
+            Ok(TempTree { root })
+        }
+
+        /// The root of the fixture tree.
+        fn root(&self) -> &Path {
+            &self.root
         }
+
+        /// Create a directory (and any parents) relative to the root.
+        fn dir(&self, rel: &str) -> Result<()> {
+            fs::create_dir_all(self.root.join(rel))?;
+            Ok(())
+        }
+
+        /// Create a small file (and any parent directories) relative to the
+        /// root.
+        fn file(&self, rel: &str) -> Result<()> {
+            let path = self.root.join(rel);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, b"x")?;
+            Ok(())
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    /// Collect a walk into a sorted list of owned paths.
+    fn sorted_paths(ff: FileFilter) -> Result<Vec<PathBuf>> {
+        let mut paths = ff
+            .map(|r| r.map(Entry::into_path))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    #[test]
+    fn test_max_open_matches_unbounded() -> Result<()> {
+        // A handful of nested directories, each holding one file.
+        let tree = TempTree::new("maxopen")?;
+        let mut dir = PathBuf::new();
+        for level in 0..5 {
+            dir = dir.join(format!("level_{level}"));
+            tree.file(dir.join(format!("file_{level}.txt")).to_str().unwrap())?;
+        }
+
+        let unbounded = sorted_paths(FileFilter::new(tree.root()))?;
+        let bounded = sorted_paths(FileFilter::new(tree.root()).max_open(1))?;
+
+        assert_eq!(unbounded, bounded);
+        assert!(!unbounded.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_window() -> Result<()> {
+        // root/a.txt (depth 1), root/sub/b.txt (depth 2).
+        let tree = TempTree::new("depth")?;
+        tree.file("a.txt")?;
+        tree.file("sub/b.txt")?;
+
+        // `max_depth(1)` keeps only the top-level file.
+        let shallow = FileFilter::new(tree.root())
+            .max_depth(1)
+            .collect::<Result<Vec<_>>>()?;
+        // `min_depth(2)` keeps only the nested file.
+        let deep = FileFilter::new(tree.root())
+            .min_depth(2)
+            .collect::<Result<Vec<_>>>()?;
+
+        assert!(shallow.iter().all(|e| e.depth() == 1));
+        assert!(shallow.iter().any(|e| e.path().ends_with("a.txt")));
+        assert!(deep.iter().all(|e| e.depth() == 2));
+        assert!(deep.iter().any(|e| e.path().ends_with("b.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_entry_prunes_subtree() -> Result<()> {
+        // root/keep.txt, root/skip/hidden.txt
+        let tree = TempTree::new("prune")?;
+        tree.file("keep.txt")?;
+        tree.file("skip/hidden.txt")?;
+
+        let entries = FileFilter::new(tree.root())
+            .filter_entry(|path| path.file_name().and_then(|n| n.to_str()) != Some("skip"))
+            .collect::<Result<Vec<_>>>()?;
+
+        assert!(entries.iter().any(|e| e.path().ends_with("keep.txt")));
+        assert!(
+            entries.iter().all(|e| !e.path().ends_with("hidden.txt")),
+            "pruned subtree must not appear"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_file_name_is_deterministic() -> Result<()> {
+        // root/{a.txt, b.txt, c.txt}
+        let tree = TempTree::new("sort")?;
+        for name in ["c.txt", "a.txt", "b.txt"] {
+            tree.file(name)?;
+        }
+
+        let names = FileFilter::new(tree.root())
+            .sort_by_file_name()
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .map(|e| e.path().file_name().unwrap().to_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_contents_first_yields_directory_last() -> Result<()> {
+        // root/sub/inner.txt
+        let tree = TempTree::new("cf")?;
+        tree.file("sub/inner.txt")?;
+
+        let entries = FileFilter::new(tree.root())
+            .sort_by_file_name()
+            .contents_first(true)
+            .collect::<Result<Vec<_>>>()?;
+
+        // The file must appear before the directory that contains it, and the
+        // root comes last of all.
+        let pos = |needle: &str| {
+            entries
+                .iter()
+                .position(|e| e.path().ends_with(needle))
+                .unwrap()
+        };
+        assert!(pos("inner.txt") < pos("sub"));
+        assert_eq!(
+            entries.last().unwrap().path(),
+            tree.root(),
+            "root is yielded last"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_parallel_matches_sequential() -> Result<()> {
+        use std::collections::HashSet;
+
+        // A small branching tree with a subtree we prune in both walks.
+        let tree = TempTree::new("par")?;
+        for dir in ["a", "a/aa", "b", "skip"] {
+            tree.dir(dir)?;
+        }
+        for file in ["a/1.txt", "a/aa/2.txt", "b/3.txt", "skip/4.txt"] {
+            tree.file(file)?;
+        }
+
+        let prune = |path: &Path| path.file_name().and_then(|n| n.to_str()) != Some("skip");
+
+        let sequential: HashSet<PathBuf> = FileFilter::new(tree.root())
+            .filter_entry(prune)
+            .add_filter(is_txt_file)
+            .map(|r| r.map(Entry::into_path))
+            .collect::<Result<_>>()?;
+
+        let parallel: HashSet<PathBuf> = FileFilter::new(tree.root())
+            .filter_entry(prune)
+            .add_filter(is_txt_file)
+            .walk_parallel_with(4)
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        assert_eq!(sequential, parallel);
+        assert!(!sequential.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_file_system_is_noop_within_one_fs() -> Result<()> {
+        // A temp tree lives on a single filesystem, so confining the walk must
+        // not change its output.
+        let tree = TempTree::new("dev")?;
+        tree.file("top.txt")?;
+        tree.file("sub/deep.txt")?;
+
+        let unconfined = sorted_paths(FileFilter::new(tree.root()))?;
+        let confined = sorted_paths(FileFilter::new(tree.root()).same_file_system(true))?;
+
+        assert_eq!(unconfined, confined);
+        assert!(!unconfined.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_parallel_does_not_follow_symlinks() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        // A symlink cycle that would spin forever if the pool followed links.
+        let tree = TempTree::new("parlink")?;
+        tree.file("inner/file.txt")?;
+        symlink("..", tree.root().join("inner").join("up"))?;
+
+        // Completing at all proves the cycle was not followed.
+        let entries = FileFilter::new(tree.root())
+            .add_filter(is_txt_file)
+            .walk_parallel_with(2)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        assert!(entries.iter().any(|p| p.ends_with("file.txt")));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links_detects_loop() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        // Build a tiny tree containing a symlink that points back at its own
+        // parent, creating a cycle once links are followed.
+        let tree = TempTree::new("loop")?;
+        tree.dir("inner")?;
+        symlink("..", tree.root().join("inner").join("up"))?;
+
+        let loop_detected = FileFilter::new(tree.root())
+            .follow_links(true)
+            .any(|entry| matches!(&entry, Err(e) if e.is::<FileFilterError>()));
+
+        assert!(loop_detected, "following the symlink loop should error");
         Ok(())
     }
 }